@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
 use std::io::{self, BufRead};
 use structopt::StructOpt;
-use terra_rust_api::{PrivateKey, Terra};
+use terra_rust_api::core_types::StdSignature;
+use terra_rust_api::{PrivateKey, PublicKey, Terra, VanityPattern, LUNA_COIN_TYPE};
 
 use crate::errors::Result;
 use bitcoin::secp256k1::All;
@@ -18,11 +20,26 @@ pub enum KeysCommand {
     New {
         #[structopt(name = "name", help = "a memorable name to use in this client")]
         name: String,
+        #[structopt(long, default_value = "0", help = "the BIP44 account to derive")]
+        account: u32,
+        #[structopt(long, default_value = "0", help = "the BIP44 index to derive")]
+        index: u32,
+        #[structopt(
+            long,
+            help = "print the recovery words to stdout (they are always stored in the keyring regardless)"
+        )]
+        show_mnemonic: bool,
     },
     /// Recover an existing key to the wallet using the recovery words
     Recover {
         #[structopt(name = "name", help = "a memorable name to use in this client")]
         name: String,
+        #[structopt(long, default_value = "0", help = "the BIP44 account to derive")]
+        account: u32,
+        #[structopt(long, default_value = "0", help = "the BIP44 index to derive")]
+        index: u32,
+        #[structopt(long, help = "print the recovery words to stdout after recovering them")]
+        show_mnemonic: bool,
     },
     /// Delete a key from the wallet
     Delete {
@@ -34,9 +51,50 @@ pub enum KeysCommand {
     Get {
         #[structopt(name = "name", help = "the key with this name.")]
         name: String,
+        #[structopt(long, default_value = "0", help = "the BIP44 account used to derive")]
+        account: u32,
+        #[structopt(long, default_value = "0", help = "the BIP44 index used to derive")]
+        index: u32,
     },
     /// List keys in the wallet
     List,
+    /// Generate a mnemonic whose account address matches a pattern
+    Vanity {
+        #[structopt(
+            name = "pattern",
+            help = "the pattern to match in the account address, after the 'terra1' prefix"
+        )]
+        pattern: String,
+        #[structopt(long, help = "match the pattern at the end of the address instead of the start")]
+        suffix: bool,
+        #[structopt(long, help = "treat the pattern as a regular expression")]
+        regex: bool,
+        #[structopt(
+            name = "name",
+            help = "optionally store the resulting key under this name"
+        )]
+        name: Option<String>,
+    },
+    /// Import a raw hex secret key (e.g. exported from another Cosmos wallet)
+    Import {
+        #[structopt(name = "name", help = "a memorable name to use in this client")]
+        name: String,
+        #[structopt(name = "secret-key", help = "the hex-encoded 32-byte secret key")]
+        secret_key: String,
+        #[structopt(long, default_value = "0", help = "the BIP44 account to record")]
+        account: u32,
+        #[structopt(long, default_value = "0", help = "the BIP44 index to record")]
+        index: u32,
+    },
+    /// Verify a signature against a message
+    Verify {
+        #[structopt(name = "message", help = "the message that was signed")]
+        message: String,
+        #[structopt(name = "signature", help = "the hex-encoded compact signature")]
+        signature: String,
+        #[structopt(name = "pubkey", help = "the hex-encoded public key that signed the message")]
+        pubkey: String,
+    },
 }
 
 macro_rules! key_format {
@@ -44,6 +102,65 @@ macro_rules! key_format {
         "TERRA-RUST-{}-{}"
     };
 }
+
+/// Marks a keyring entry as holding a raw hex secret key (from `keys import`) rather than a
+/// mnemonic, so `get_private_key` knows which constructor to use when loading it back. The
+/// `account`/`index` the key was imported with are stored alongside the hex key itself
+/// (`HEXKEY:<account>:<index>:<hex>`), since a raw key has no derivation tree to re-select them
+/// from the way a mnemonic-backed key does.
+const RAW_KEY_PREFIX: &str = "HEXKEY:";
+
+/// OS keyrings don't all support enumerating their entries, so we maintain a small index of the
+/// keys stored for a wallet as a keyring entry of its own, updated on `New`/`Recover`/`Vanity`
+/// (when named)/`Import`/`Delete`. Recording `account`/`index` here (rather than just the name)
+/// lets `List` reconstruct the same address each key was created with, instead of guessing.
+#[derive(Serialize, Deserialize)]
+struct KeyIndexEntry {
+    name: String,
+    account: u32,
+    index: u32,
+}
+
+fn index_keyname(wallet: &str) -> String {
+    format!(key_format!(), wallet, "KEY-INDEX")
+}
+
+fn load_key_index(wallet: &str) -> Vec<KeyIndexEntry> {
+    let keyring = keyring::Keyring::new(&wallet, &index_keyname(wallet));
+    match keyring.get_password() {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_key_index(wallet: &str, entries: &[KeyIndexEntry]) -> Result<()> {
+    let keyring = keyring::Keyring::new(&wallet, &index_keyname(wallet));
+    let json = serde_json::to_string(entries).map_err(|e| format!("{}", e))?;
+    keyring.set_password(&json)?;
+    Ok(())
+}
+
+fn add_to_key_index(wallet: &str, name: &str, account: u32, index: u32) -> Result<()> {
+    let mut entries = load_key_index(wallet);
+    match entries.iter_mut().find(|e| e.name == name) {
+        Some(entry) => {
+            entry.account = account;
+            entry.index = index;
+        }
+        None => entries.push(KeyIndexEntry {
+            name: name.to_string(),
+            account,
+            index,
+        }),
+    }
+    save_key_index(wallet, &entries)
+}
+
+fn remove_from_key_index(wallet: &str, name: &str) -> Result<()> {
+    let mut entries = load_key_index(wallet);
+    entries.retain(|e| e.name != name);
+    save_key_index(wallet, &entries)
+}
 pub fn key_cmd_parse(
     _terra: &Terra,
     wallet: &str,
@@ -54,7 +171,12 @@ pub fn key_cmd_parse(
         KeysCommand::Parse { hex } => {
             println!("{}", hex)
         }
-        KeysCommand::Recover { name } => {
+        KeysCommand::Recover {
+            name,
+            account,
+            index,
+            show_mnemonic,
+        } => {
             let secp = Secp256k1::new();
             let keyname = format!(key_format!(), wallet, name);
 
@@ -80,27 +202,34 @@ pub fn key_cmd_parse(
 
             let pk = match seed {
                 Some(seed_str) => PrivateKey::from_words_seed(&secp, &words, seed_str)?,
-                None => PrivateKey::from_words(&secp, &words)?,
+                None => PrivateKey::from_words_indexed(&secp, &words, account, index)?,
             };
             let keyring = keyring::Keyring::new(&wallet, &keyname);
             keyring.set_password(pk.words().unwrap())?;
+            add_to_key_index(wallet, &name, account, index)?;
+            if show_mnemonic {
+                println!("Your recovery words are:");
+                println!("{}", pk.words().unwrap());
+            }
         }
 
-        KeysCommand::New { name } => {
+        KeysCommand::New {
+            name,
+            account,
+            index,
+            show_mnemonic,
+        } => {
             let secp = Secp256k1::new();
 
             println!("This key will be stored in your computer's vault (win10)/secret service (linux)/keyring (os/x)");
 
             let pk = match seed {
-                None => PrivateKey::new(&secp)?,
+                None => PrivateKey::new_indexed(&secp, account, index)?,
                 Some(seed_str) => PrivateKey::new_seed(&secp, seed_str)?,
             };
             println!("Please write these down and save these in a secure location.");
             println!("These words can be used to transfer all your coins out of your account");
             println!("NO ONE has a need for these keys except you. If they are asking for them it is a scam.");
-            println!();
-            println!("Your recovery words are:");
-            println!("{}", pk.words().unwrap());
             if seed.is_some() {
                 println!("Please also take note of your seed phrase");
             }
@@ -108,20 +237,132 @@ pub fn key_cmd_parse(
             let keyname = format!(key_format!(), wallet, name);
             let keyring = keyring::Keyring::new(&wallet, &keyname);
             keyring.set_password(pk.words().unwrap())?;
+            add_to_key_index(wallet, &name, account, index)?;
+
+            if show_mnemonic {
+                println!();
+                println!("Your recovery words are:");
+                println!("{}", pk.words().unwrap());
+            } else {
+                println!();
+                println!("Recovery words have been stored in your keyring. Pass --show-mnemonic to print them.");
+            }
         }
         KeysCommand::Delete { name } => {
             let keyname = format!(key_format!(), wallet, name);
             let keyring = keyring::Keyring::new(&wallet, &keyname);
             keyring.delete_password()?;
+            remove_from_key_index(wallet, &name)?;
         }
-        KeysCommand::Get { name } => {
+        KeysCommand::Get {
+            name,
+            account,
+            index,
+        } => {
             let secp = Secp256k1::new();
-            let priv_key = get_private_key(&secp, wallet, &name, seed)?;
+            let priv_key = get_private_key(&secp, wallet, &name, seed, account, index)?;
             let pub_key = priv_key.public_key(&secp);
             println!("{}", pub_key.account()?)
         }
         KeysCommand::List => {
-            todo!()
+            let entries = load_key_index(wallet);
+            if entries.is_empty() {
+                println!("No keys stored for wallet '{}'", wallet);
+            } else {
+                let secp = Secp256k1::new();
+                println!("{:<20} {:<46} {:<51}", "NAME", "ACCOUNT", "VALOPER");
+                for entry in entries {
+                    match get_private_key(&secp, wallet, &entry.name, seed, entry.account, entry.index) {
+                        Ok(priv_key) => {
+                            let pub_key = priv_key.public_key(&secp);
+                            let account = pub_key.account().unwrap_or_default();
+                            let valoper = pub_key.operator_address().unwrap_or_default();
+                            println!("{:<20} {:<46} {:<51}", entry.name, account, valoper);
+                        }
+                        Err(e) => println!("{:<20} <error reading key: {}>", entry.name, e),
+                    }
+                }
+            }
+        }
+        KeysCommand::Vanity {
+            pattern,
+            suffix,
+            regex,
+            name,
+        } => {
+            let vanity_pattern = if regex {
+                VanityPattern::regex(&pattern)?
+            } else if suffix {
+                VanityPattern::suffix(&pattern)?
+            } else {
+                VanityPattern::prefix(&pattern)?
+            };
+
+            println!("Searching for a matching address. This can take a while...");
+            let pk = PrivateKey::vanity(&vanity_pattern)?;
+            let pub_key = pk.public_key(&Secp256k1::new());
+            println!("Account: {}", pub_key.account()?);
+            println!("Recovery words: {}", pk.words().unwrap());
+
+            if let Some(name) = name {
+                let keyname = format!(key_format!(), wallet, &name);
+                let keyring = keyring::Keyring::new(&wallet, &keyname);
+                keyring.set_password(pk.words().unwrap())?;
+                add_to_key_index(wallet, &name, 0, 0)?;
+            }
+        }
+        KeysCommand::Import {
+            name,
+            secret_key,
+            account,
+            index,
+        } => {
+            let secp = Secp256k1::new();
+            let key_bytes = hex::decode(&secret_key).map_err(|e| format!("{}", e))?;
+            if key_bytes.len() != 32 {
+                return Err(format!(
+                    "expected a 32 byte hex secret key, got {} bytes",
+                    key_bytes.len()
+                )
+                .into());
+            }
+            let mut raw = [0u8; 32];
+            raw.copy_from_slice(&key_bytes);
+            // make sure the bytes are actually a valid secret key before storing them
+            PrivateKey::from_raw_key(
+                &secp,
+                &raw,
+                account,
+                index,
+                LUNA_COIN_TYPE,
+            )?;
+
+            let keyname = format!(key_format!(), wallet, name);
+            let keyring = keyring::Keyring::new(&wallet, &keyname);
+            keyring.set_password(&format!(
+                "{}{}:{}:{}",
+                RAW_KEY_PREFIX, account, index, secret_key
+            ))?;
+            add_to_key_index(wallet, &name, account, index)?;
+            println!("Imported key '{}'", name);
+        }
+        KeysCommand::Verify {
+            message,
+            signature,
+            pubkey,
+        } => {
+            let secp = Secp256k1::new();
+            let sig_bytes = hex::decode(&signature).map_err(|e| format!("{}", e))?;
+            let pubkey_bytes = hex::decode(&pubkey).map_err(|e| format!("{}", e))?;
+            let bitcoin_pub_key = bitcoin::util::key::PublicKey::from_slice(&pubkey_bytes)
+                .map_err(|e| format!("{}", e))?;
+            let std_sig = StdSignature::create(&sig_bytes, &bitcoin_pub_key);
+
+            if PublicKey::verify(&secp, &message, &std_sig)? {
+                println!("VALID");
+            } else {
+                println!("INVALID");
+            }
         }
     }
     Ok(())
@@ -132,13 +373,49 @@ pub fn get_private_key(
     wallet: &str,
     name: &str,
     seed: Option<&str>,
+    account: u32,
+    index: u32,
 ) -> Result<PrivateKey> {
     let keyname = format!(key_format!(), wallet, name);
     let keyring = keyring::Keyring::new(&wallet, &keyname);
     let phrase = keyring.get_password()?;
-    log::error!("{}", &phrase);
+    log::debug!("loaded private key '{}' from keyring", name);
+
+    if let Some(rest) = phrase.strip_prefix(RAW_KEY_PREFIX) {
+        let mut parts = rest.splitn(3, ':');
+        let (stored_account, stored_index, hex_key) =
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(a), Some(i), Some(hex_key)) => (
+                    a.parse::<u32>().map_err(|e| format!("{}", e))?,
+                    i.parse::<u32>().map_err(|e| format!("{}", e))?,
+                    hex_key,
+                ),
+                // keys imported before account/index were recorded alongside the hex key
+                _ => (account, index, rest),
+            };
+        let key_bytes = hex::decode(hex_key).map_err(|e| format!("{}", e))?;
+        if key_bytes.len() != 32 {
+            return Err(format!(
+                "expected a 32 byte hex secret key, got {} bytes",
+                key_bytes.len()
+            )
+            .into());
+        }
+        let mut raw = [0u8; 32];
+        raw.copy_from_slice(&key_bytes);
+        return Ok(PrivateKey::from_raw_key(
+            secp,
+            &raw,
+            stored_account,
+            stored_index,
+            LUNA_COIN_TYPE,
+        )?);
+    }
+
     match seed {
-        None => Ok(PrivateKey::from_words(secp, &phrase)?),
+        None => Ok(PrivateKey::from_words_indexed(
+            secp, &phrase, account, index,
+        )?),
         Some(seed_str) => Ok(PrivateKey::from_words_seed(secp, &phrase, seed_str)?),
     }
 }
@@ -10,17 +10,114 @@ use crypto::sha2::Sha256;
 use crypto::digest::Digest;
 use hkd32::mnemonic::{Phrase, Seed};
 use rand_core::OsRng;
+use regex::Regex;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use zeroize::Zeroizing;
 
 pub static LUNA_COIN_TYPE: u32 = 330;
 
+/// The bech32 charset (see BIP-0173), used to validate vanity patterns up front.
+pub static BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// How a [`PrivateKey::vanity`] search should match the generated account address.
+/// Every variant is matched against the part of the address after the `terra1` prefix.
+#[derive(Clone)]
+pub enum VanityPattern {
+    /// the address (after `terra1`) must start with this string
+    Prefix(String),
+    /// the address must end with this string
+    Suffix(String),
+    /// the address (after `terra1`) must match this regex
+    Regex(Regex),
+}
+
+impl VanityPattern {
+    /// Match addresses whose part after `terra1` starts with `pattern`.
+    pub fn prefix(pattern: &str) -> Result<VanityPattern> {
+        VanityPattern::check_charset(pattern)?;
+        Ok(VanityPattern::Prefix(pattern.to_string()))
+    }
+    /// Match addresses ending with `pattern`.
+    pub fn suffix(pattern: &str) -> Result<VanityPattern> {
+        VanityPattern::check_charset(pattern)?;
+        Ok(VanityPattern::Suffix(pattern.to_string()))
+    }
+    /// Match the part of the address after `terra1` against an arbitrary regex.
+    pub fn regex(pattern: &str) -> Result<VanityPattern> {
+        match Regex::new(pattern) {
+            Ok(re) => Ok(VanityPattern::Regex(re)),
+            Err(_) => Err(ErrorKind::Phrasing.into()),
+        }
+    }
+    fn check_charset(pattern: &str) -> Result<()> {
+        if pattern.chars().all(|c| BECH32_CHARSET.contains(c)) {
+            Ok(())
+        } else {
+            Err(ErrorKind::Bech32DecodeErr.into())
+        }
+    }
+    fn matches(&self, address: &str) -> bool {
+        match self {
+            VanityPattern::Prefix(p) => address.starts_with(p.as_str()),
+            VanityPattern::Suffix(p) => address.ends_with(p.as_str()),
+            VanityPattern::Regex(re) => re.is_match(address),
+        }
+    }
+}
+
+/// The non-secret BIP32 bookkeeping for an `ExtendedPrivKey`, plus a zeroized copy of its secret.
+#[derive(Clone)]
+struct ExtendedKeyMaterial {
+    network: Network,
+    depth: u8,
+    parent_fingerprint: bitcoin::util::bip32::Fingerprint,
+    child_number: bitcoin::util::bip32::ChildNumber,
+    chain_code: bitcoin::util::bip32::ChainCode,
+    secret: Zeroizing<[u8; 32]>,
+}
+
+impl ExtendedKeyMaterial {
+    fn capture(epk: &ExtendedPrivKey) -> ExtendedKeyMaterial {
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&epk.private_key.key[..]);
+        ExtendedKeyMaterial {
+            network: epk.network,
+            depth: epk.depth,
+            parent_fingerprint: epk.parent_fingerprint,
+            child_number: epk.child_number,
+            chain_code: epk.chain_code,
+            secret: Zeroizing::new(secret),
+        }
+    }
+
+    fn to_extended_priv_key(&self) -> ExtendedPrivKey {
+        ExtendedPrivKey {
+            network: self.network,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            private_key: bitcoin::util::key::PrivateKey {
+                compressed: true,
+                network: self.network,
+                key: bitcoin::secp256k1::SecretKey::from_slice(&*self.secret)
+                    .expect("captured secret key bytes are always a valid secp256k1 scalar"),
+            },
+            chain_code: self.chain_code,
+        }
+    }
+}
+
 pub struct PrivateKey {
     pub account: u32,
     pub index: u32,
     pub coin_type: u32,
     mnemonic: Option<Phrase>,
 
-    root_private_key: ExtendedPrivKey,
-    private_key: ExtendedPrivKey,
+    root_key_material: ExtendedKeyMaterial,
+    key_material: ExtendedKeyMaterial,
 }
 impl PrivateKey {
     pub fn new<'a>(secp: &Secp256k1<All>) -> Result<PrivateKey> {
@@ -39,11 +136,139 @@ impl PrivateKey {
             Err(_) => Err(ErrorKind::Phrasing.into()),
         }
     }
+    /// Same as [`PrivateKey::new`], but derives a specific `account`/`index`.
+    pub fn new_indexed(secp: &Secp256k1<All>, account: u32, index: u32) -> Result<PrivateKey> {
+        let phrase =
+            hkd32::mnemonic::Phrase::random(&mut OsRng, hkd32::mnemonic::Language::English);
+        PrivateKey::gen_private_key_phrase(secp, phrase, account, index, LUNA_COIN_TYPE)
+    }
+    /// Same as [`PrivateKey::new`], but derives using `coin_type` instead of [`LUNA_COIN_TYPE`].
+    pub fn new_coin_type(secp: &Secp256k1<All>, coin_type: u32) -> Result<PrivateKey> {
+        let phrase =
+            hkd32::mnemonic::Phrase::random(&mut OsRng, hkd32::mnemonic::Language::English);
+        PrivateKey::gen_private_key_phrase(secp, phrase, 0, 0, coin_type)
+    }
+    /// Same as [`PrivateKey::from_words`], but derives a specific `account`/`index`.
+    pub fn from_words_indexed(
+        secp: &Secp256k1<All>,
+        words: &str,
+        account: u32,
+        index: u32,
+    ) -> Result<PrivateKey> {
+        match hkd32::mnemonic::Phrase::new(words, hkd32::mnemonic::Language::English) {
+            Ok(phrase) => PrivateKey::gen_private_key_phrase(secp, phrase, account, index, LUNA_COIN_TYPE),
+            Err(_) => Err(ErrorKind::Phrasing.into()),
+        }
+    }
+    /// Searches for a vanity account address across all available CPU cores.
+    pub fn vanity(pattern: &VanityPattern) -> Result<PrivateKey> {
+        let num_threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        let found = Arc::new(AtomicBool::new(false));
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let result: Arc<Mutex<Option<PrivateKey>>> = Arc::new(Mutex::new(None));
+
+        let handles: Vec<_> = (0..num_threads)
+            .map(|_| {
+                let found = Arc::clone(&found);
+                let attempts = Arc::clone(&attempts);
+                let result = Arc::clone(&result);
+                let pattern = pattern.clone();
+                thread::spawn(move || {
+                    let secp = Secp256k1::new();
+                    while !found.load(Ordering::Relaxed) {
+                        let phrase = hkd32::mnemonic::Phrase::random(
+                            &mut OsRng,
+                            hkd32::mnemonic::Language::English,
+                        );
+                        let pk = match PrivateKey::gen_private_key_phrase(
+                            &secp,
+                            phrase,
+                            0,
+                            0,
+                            LUNA_COIN_TYPE,
+                        ) {
+                            Ok(pk) => pk,
+                            Err(_) => continue,
+                        };
+
+                        let attempt_no = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+                        if attempt_no % 10_000 == 0 {
+                            log::info!("vanity: {} addresses tried so far", attempt_no);
+                        }
+
+                        let account = match pk.public_key(&secp).account() {
+                            Ok(account) => account,
+                            Err(_) => continue,
+                        };
+                        if let Some(suffix) = account.strip_prefix("terra1") {
+                            if pattern.matches(suffix) && !found.swap(true, Ordering::SeqCst) {
+                                *result.lock().unwrap() = Some(pk);
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        result.lock().unwrap().take().ok_or_else(|| ErrorKind::Implementation.into())
+    }
+
     pub fn public_key(&self, secp: &Secp256k1<All>) -> PublicKey {
-        let x = &self.private_key.private_key.public_key(secp);
+        let private_key = self.key_material.to_extended_priv_key();
+        let x = &private_key.private_key.public_key(secp);
         PublicKey::from_bitcoin_public_key(x)
     }
 
+    /// Builds a `PrivateKey` directly from 32 raw secret key bytes, with no mnemonic.
+    pub fn from_raw_key(
+        _secp: &Secp256k1<All>,
+        raw_key: &[u8; 32],
+        account: u32,
+        index: u32,
+        coin_type: u32,
+    ) -> Result<PrivateKey> {
+        let secret_key = bitcoin::secp256k1::SecretKey::from_slice(raw_key)?;
+        let btc_private_key = bitcoin::util::key::PrivateKey {
+            compressed: true,
+            network: Network::Bitcoin,
+            key: secret_key,
+        };
+        let extended_priv_key = ExtendedPrivKey {
+            network: Network::Bitcoin,
+            depth: 0,
+            parent_fingerprint: Default::default(),
+            child_number: bitcoin::util::bip32::ChildNumber::from_normal_idx(0)?,
+            private_key: btc_private_key,
+            chain_code: bitcoin::util::bip32::ChainCode::from(&[0u8; 32][..]),
+        };
+        let key_material = ExtendedKeyMaterial::capture(&extended_priv_key);
+        Ok(PrivateKey {
+            account,
+            index,
+            coin_type,
+            mnemonic: None,
+
+            root_key_material: key_material.clone(),
+            key_material,
+        })
+    }
+
+    /// The raw 32-byte secret key, from a mnemonic or [`PrivateKey::from_raw_key`].
+    pub fn raw_key_bytes(&self) -> [u8; 32] {
+        *self.key_material.secret
+    }
+
+    /// Hex export of the raw secret key, for moving a key to another Cosmos wallet.
+    pub fn export_hex(&self) -> String {
+        hex::encode(self.raw_key_bytes())
+    }
+
     fn gen_private_key_phrase(
         secp: &Secp256k1<All>,
         phrase: Phrase,
@@ -64,8 +289,8 @@ impl PrivateKey {
             coin_type,
             mnemonic: Some(phrase),
 
-            root_private_key,
-            private_key,
+            root_key_material: ExtendedKeyMaterial::capture(&root_private_key),
+            key_material: ExtendedKeyMaterial::capture(&private_key),
         })
     }
 
@@ -82,8 +307,9 @@ impl PrivateKey {
         }
     }
     pub fn sign(&self, secp: &Secp256k1<All>, blob: &str) -> Result<StdSignature> {
-        let pub_k = &self.private_key.private_key.public_key(secp);
-        let priv_k = self.private_key.private_key.key;
+        let private_key = self.key_material.to_extended_priv_key();
+        let pub_k = &private_key.private_key.public_key(secp);
+        let priv_k = private_key.private_key.key;
         let mut sha = Sha256::new();
         let mut sha_result: [u8; 32] = [0; 32];
         sha.input_str(blob);
@@ -98,6 +324,30 @@ impl PrivateKey {
     }
 }
 
+/// Redacts the mnemonic and key material rather than printing them verbatim.
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("account", &self.account)
+            .field("index", &self.index)
+            .field("coin_type", &self.coin_type)
+            .field("mnemonic", &"<redacted>")
+            .field("root_key_material", &"<redacted>")
+            .field("key_material", &"<redacted>")
+            .finish()
+    }
+}
+
+impl fmt::Display for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PrivateKey(account={}, index={}, coin_type={}, <redacted>)",
+            self.account, self.index, self.coin_type
+        )
+    }
+}
+
 #[cfg(test)]
 mod tst {
     use super::*;
@@ -131,10 +381,10 @@ mod tst {
         let secp = Secp256k1::new();
         let pk = PrivateKey::from_words(&secp, str_1)?;
         let root_key = "xprv9s21ZrQH143K2ep3BpYRRMjSqjLHZAPAzxfVVS3NBuGKBVtCrK3C8mE8TcmTjYnLm7SJxdLigDFWGAMnctKxc3p5QKNWXdprcFSQzGzQqTW";
-        assert_eq!(pk.root_private_key.to_string(), root_key);
+        assert_eq!(pk.root_key_material.to_extended_priv_key().to_string(), root_key);
 
         let derived_key = "4804e2bdce36d413206ccf47cc4c64db2eff924e7cc9e90339fa7579d2bd9d5b";
-        assert_eq!(pk.private_key.private_key.key.to_string(), derived_key);
+        assert_eq!(hex::encode(pk.key_material.secret.as_slice()), derived_key);
 
         Ok(())
     }
@@ -192,4 +442,32 @@ mod tst {
         println!("{}", serde_json::to_string_pretty(&sig).unwrap());
         Ok(())
     }
+    #[test]
+    pub fn test_sign_verify_roundtrip() -> Result<()> {
+        let str_1 = "island relax shop such yellow opinion find know caught erode blue dolphin behind coach tattoo light focus snake common size analyst imitate employ walnut";
+        let secp = Secp256k1::new();
+        let pk = PrivateKey::from_words(&secp, str_1)?;
+        let blob = "hello terra";
+        let sig = pk.sign(&secp, blob)?;
+        assert!(PublicKey::verify(&secp, blob, &sig)?);
+        assert!(!PublicKey::verify(&secp, "a different message", &sig)?);
+        Ok(())
+    }
+    #[test]
+    pub fn test_from_raw_key_roundtrip() -> Result<()> {
+        let str_1 = "island relax shop such yellow opinion find know caught erode blue dolphin behind coach tattoo light focus snake common size analyst imitate employ walnut";
+        let secp = Secp256k1::new();
+        let original = PrivateKey::from_words(&secp, str_1)?;
+        let raw = original.raw_key_bytes();
+        let hex_key = original.export_hex();
+
+        let imported = PrivateKey::from_raw_key(&secp, &raw, 0, 0, LUNA_COIN_TYPE)?;
+        assert_eq!(imported.raw_key_bytes(), raw);
+        assert_eq!(imported.export_hex(), hex_key);
+        assert_eq!(
+            imported.public_key(&secp).account()?,
+            original.public_key(&secp).account()?
+        );
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
+use crate::core_types::StdSignature;
 use crate::errors::{ErrorKind, Result};
 
 use bitcoin::bech32::{decode, encode, u5, FromBase32, ToBase32};
+use bitcoin::secp256k1::{All, Message, PublicKey as Secp256k1PublicKey, Secp256k1, Signature};
 use crypto::digest::Digest;
 use crypto::ripemd160::Ripemd160;
 use crypto::sha2::Sha256;
@@ -202,6 +204,30 @@ impl PublicKey {
             None => Err(ErrorKind::Implementation.into()),
         }
     }
+    /// Verifies a signature produced by `PrivateKey::sign` against `blob`.
+    pub fn verify(secp: &Secp256k1<All>, blob: &str, signature: &StdSignature) -> Result<bool> {
+        let mut sha = Sha256::new();
+        let mut sha_result: [u8; 32] = [0; 32];
+        sha.input_str(blob);
+        sha.result(&mut sha_result);
+
+        let message = Message::from_slice(&sha_result)?;
+
+        let sig_bytes = base64::decode(&signature.signature)
+            .map_err(|_| ErrorKind::Conversion(signature.signature.clone()))?;
+        let sig = Signature::from_compact(&sig_bytes)?;
+
+        let pub_key_bytes = base64::decode(&signature.pub_key.value)
+            .map_err(|_| ErrorKind::Conversion(signature.pub_key.value.clone()))?;
+        let pub_key = Secp256k1PublicKey::from_slice(&pub_key_bytes)?;
+
+        Ok(secp.verify(&message, &sig, &pub_key).is_ok())
+    }
+}
+
+/// Standalone equivalent of `PublicKey::verify`.
+pub fn verify(secp: &Secp256k1<All>, blob: &str, signature: &StdSignature) -> Result<bool> {
+    PublicKey::verify(secp, blob, signature)
 }
 #[cfg(test)]
 mod tst {